@@ -0,0 +1,37 @@
+pub mod resource;
+pub mod system;
+
+pub use resource::*;
+pub use system::*;
+
+/// A type that can be stored as a resource in a [Resources] collection.
+///
+/// Resources are not required to be `Send + Sync` on their own — [Res]/[ResMut] add that bound
+/// explicitly, where it's actually needed, so [NonSend]/[NonSendMut] can still store
+/// non-thread-safe state like GPU handles, OS window objects, or `Rc`-based data.
+pub trait Resource: 'static {}
+impl<T: 'static> Resource for T {}
+
+/// Per-resource (and per-component) change-detection bits. `ADDED` is set when a value is
+/// inserted and `MUTATED` when it is mutated through [ResMut] or [Local] (mutation through
+/// [NonSendMut] does not set it). Both are cleared again at the end of each update by the thing
+/// that owns the value (`Resources::clear_trackers` for resources).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ComponentFlags(u8);
+
+impl ComponentFlags {
+    pub const ADDED: Self = Self(1 << 0);
+    pub const MUTATED: Self = Self(1 << 1);
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    pub fn clear(&mut self) {
+        self.0 = 0;
+    }
+}
@@ -0,0 +1,20 @@
+mod resource_query;
+mod resources;
+
+pub use resource_query::*;
+pub use resources::*;
+
+use crate::Resource;
+
+/// Constructs a default value of a resource, given access to the other resources already present
+/// in a [Resources] collection. Used by [Local] (and [ResourceEntry::or_from_resources]) to lazily
+/// initialize resources that may themselves depend on other resources.
+pub trait FromResources {
+    fn from_resources(resources: &Resources) -> Self;
+}
+
+impl<T: Resource + Default> FromResources for T {
+    fn from_resources(_resources: &Resources) -> Self {
+        Self::default()
+    }
+}
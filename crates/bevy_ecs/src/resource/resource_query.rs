@@ -1,7 +1,7 @@
 use super::{FromResources, Resources};
 use crate::{
     system::{SystemId, TypeAccess},
-    Resource, ResourceIndex,
+    ComponentFlags, Resource, ResourceIndex,
 };
 use core::{
     any::TypeId,
@@ -11,6 +11,36 @@ use core::{
 use hecs::smaller_tuples_too;
 use std::marker::PhantomData;
 
+/// Uniquely identifies a resource type, naming it in borrow-conflict panic messages
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct ResourceTypeId {
+    pub type_id: TypeId,
+    #[cfg(debug_assertions)]
+    pub type_name: &'static str,
+}
+
+impl ResourceTypeId {
+    pub fn of<T: Resource>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            #[cfg(debug_assertions)]
+            type_name: core::any::type_name::<T>(),
+        }
+    }
+}
+
+impl std::fmt::Display for ResourceTypeId {
+    #[cfg(debug_assertions)]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.type_name)
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.type_id)
+    }
+}
+
 /// Shared borrow of a Resource
 pub struct Res<'a, T: Resource> {
     value: &'a T,
@@ -24,6 +54,36 @@ impl<'a, T: Resource> Res<'a, T> {
     }
 }
 
+/// Shared borrow of a Resource, for systems that should only run when it changed
+pub struct ChangedRes<'a, T: Resource> {
+    value: &'a T,
+}
+
+impl<'a, T: Resource> ChangedRes<'a, T> {
+    pub unsafe fn new(value: NonNull<T>) -> Self {
+        Self {
+            value: &*value.as_ptr(),
+        }
+    }
+}
+
+impl<'a, T: Resource> UnsafeClone for ChangedRes<'a, T> {
+    unsafe fn unsafe_clone(&self) -> Self {
+        Self { value: self.value }
+    }
+}
+
+unsafe impl<T: Resource + Send + Sync> Send for ChangedRes<'_, T> {}
+unsafe impl<T: Resource + Send + Sync> Sync for ChangedRes<'_, T> {}
+
+impl<'a, T: Resource> Deref for ChangedRes<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
 /// A clone that is unsafe to perform. You probably shouldn't use this.
 pub trait UnsafeClone {
     unsafe fn unsafe_clone(&self) -> Self;
@@ -35,8 +95,8 @@ impl<'a, T: Resource> UnsafeClone for Res<'a, T> {
     }
 }
 
-unsafe impl<T: Resource> Send for Res<'_, T> {}
-unsafe impl<T: Resource> Sync for Res<'_, T> {}
+unsafe impl<T: Resource + Send + Sync> Send for Res<'_, T> {}
+unsafe impl<T: Resource + Send + Sync> Sync for Res<'_, T> {}
 
 impl<'a, T: Resource> Deref for Res<'a, T> {
     type Target = T;
@@ -50,19 +110,21 @@ impl<'a, T: Resource> Deref for Res<'a, T> {
 pub struct ResMut<'a, T: Resource> {
     _marker: PhantomData<&'a T>,
     value: *mut T,
+    flags: *mut ComponentFlags,
 }
 
 impl<'a, T: Resource> ResMut<'a, T> {
-    pub unsafe fn new(value: NonNull<T>) -> Self {
+    pub unsafe fn new(value: NonNull<T>, flags: NonNull<ComponentFlags>) -> Self {
         Self {
             value: value.as_ptr(),
+            flags: flags.as_ptr(),
             _marker: Default::default(),
         }
     }
 }
 
-unsafe impl<T: Resource> Send for ResMut<'_, T> {}
-unsafe impl<T: Resource> Sync for ResMut<'_, T> {}
+unsafe impl<T: Resource + Send + Sync> Send for ResMut<'_, T> {}
+unsafe impl<T: Resource + Send + Sync> Sync for ResMut<'_, T> {}
 
 impl<'a, T: Resource> Deref for ResMut<'a, T> {
     type Target = T;
@@ -74,11 +136,86 @@ impl<'a, T: Resource> Deref for ResMut<'a, T> {
 
 impl<'a, T: Resource> DerefMut for ResMut<'a, T> {
     fn deref_mut(&mut self) -> &mut T {
-        unsafe { &mut *self.value }
+        unsafe {
+            (*self.flags).insert(ComponentFlags::MUTATED);
+            &mut *self.value
+        }
     }
 }
 
 impl<'a, T: Resource> UnsafeClone for ResMut<'a, T> {
+    unsafe fn unsafe_clone(&self) -> Self {
+        Self {
+            value: self.value,
+            flags: self.flags,
+            _marker: Default::default(),
+        }
+    }
+}
+
+impl<T: UnsafeClone> UnsafeClone for Option<T> {
+    unsafe fn unsafe_clone(&self) -> Self {
+        self.as_ref().map(|value| value.unsafe_clone())
+    }
+}
+
+/// Shared borrow of a non-`Send` resource, usable only on the thread that owns `Resources`
+pub struct NonSend<'a, T: Resource> {
+    value: &'a T,
+}
+
+impl<'a, T: Resource> NonSend<'a, T> {
+    pub unsafe fn new(value: NonNull<T>) -> Self {
+        Self {
+            value: &*value.as_ptr(),
+        }
+    }
+}
+
+impl<'a, T: Resource> UnsafeClone for NonSend<'a, T> {
+    unsafe fn unsafe_clone(&self) -> Self {
+        Self { value: self.value }
+    }
+}
+
+impl<'a, T: Resource> Deref for NonSend<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+/// Unique borrow of a non-`Send` resource, usable only on the thread that owns `Resources`
+pub struct NonSendMut<'a, T: Resource> {
+    _marker: PhantomData<&'a T>,
+    value: *mut T,
+}
+
+impl<'a, T: Resource> NonSendMut<'a, T> {
+    pub unsafe fn new(value: NonNull<T>) -> Self {
+        Self {
+            value: value.as_ptr(),
+            _marker: Default::default(),
+        }
+    }
+}
+
+impl<'a, T: Resource> Deref for NonSendMut<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.value }
+    }
+}
+
+impl<'a, T: Resource> DerefMut for NonSendMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.value }
+    }
+}
+
+impl<'a, T: Resource> UnsafeClone for NonSendMut<'a, T> {
     unsafe fn unsafe_clone(&self) -> Self {
         Self {
             value: self.value,
@@ -91,6 +228,7 @@ impl<'a, T: Resource> UnsafeClone for ResMut<'a, T> {
 /// Local resources are automatically initialized using the FromResources trait.
 pub struct Local<'a, T: Resource + FromResources> {
     value: *mut T,
+    flags: *mut ComponentFlags,
     _marker: PhantomData<&'a T>,
 }
 
@@ -98,6 +236,7 @@ impl<'a, T: Resource + FromResources> UnsafeClone for Local<'a, T> {
     unsafe fn unsafe_clone(&self) -> Self {
         Self {
             value: self.value,
+            flags: self.flags,
             _marker: Default::default(),
         }
     }
@@ -113,7 +252,69 @@ impl<'a, T: Resource + FromResources> Deref for Local<'a, T> {
 
 impl<'a, T: Resource + FromResources> DerefMut for Local<'a, T> {
     fn deref_mut(&mut self) -> &mut T {
-        unsafe { &mut *self.value }
+        unsafe {
+            (*self.flags).insert(ComponentFlags::MUTATED);
+            &mut *self.value
+        }
+    }
+}
+
+/// A view into a single resource slot of a `Resources` collection, returned by `Resources::entry`
+pub struct ResourceEntry<'a, T: Resource> {
+    resources: &'a mut Resources,
+    _marker: PhantomData<T>,
+}
+
+impl<'a, T: Resource> ResourceEntry<'a, T> {
+    pub fn new(resources: &'a mut Resources) -> Self {
+        Self {
+            resources,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Inserts `value` if the resource is not already present, then returns a mutable borrow of it.
+    pub fn or_insert(self, value: T) -> ResMut<'a, T> {
+        self.or_insert_with(|| value)
+    }
+
+    /// Inserts `T::default()` if the resource is not already present, then returns a mutable borrow of it.
+    pub fn or_default(self) -> ResMut<'a, T>
+    where
+        T: Default,
+    {
+        self.or_insert_with(T::default)
+    }
+
+    /// Inserts the result of `f` if the resource is not already present, then returns a mutable borrow of it.
+    pub fn or_insert_with(self, f: impl FnOnce() -> T) -> ResMut<'a, T> {
+        if !self.resources.contains::<T>() {
+            self.resources.insert(f());
+        }
+        unsafe {
+            ResMut::new(
+                self.resources.get_unsafe_ref::<T>(ResourceIndex::Global),
+                self.resources.get_flags::<T>(ResourceIndex::Global),
+            )
+        }
+    }
+
+    /// Inserts a value built with [FromResources] if the resource is not already present, then
+    /// returns a mutable borrow of it.
+    pub fn or_from_resources(self) -> ResMut<'a, T>
+    where
+        T: FromResources,
+    {
+        if !self.resources.contains::<T>() {
+            let value = T::from_resources(self.resources);
+            self.resources.insert(value);
+        }
+        unsafe {
+            ResMut::new(
+                self.resources.get_unsafe_ref::<T>(ResourceIndex::Global),
+                self.resources.get_flags::<T>(ResourceIndex::Global),
+            )
+        }
     }
 }
 
@@ -160,7 +361,85 @@ impl<'a, T: Resource> FetchResource<'a> for FetchResourceRead<T> {
 
     fn access() -> TypeAccess {
         let mut access = TypeAccess::default();
-        access.immutable.insert(TypeId::of::<T>());
+        access.immutable.insert(ResourceTypeId::of::<T>());
+        access
+    }
+}
+
+impl<'a, T: Resource> ResourceQuery for Option<Res<'a, T>> {
+    type Fetch = FetchResourceOptionRead<T>;
+}
+
+/// Fetches a shared resource reference, or `None` if the resource does not exist
+pub struct FetchResourceOptionRead<T>(NonNull<T>);
+
+impl<'a, T: Resource> FetchResource<'a> for FetchResourceOptionRead<T> {
+    type Item = Option<Res<'a, T>>;
+
+    unsafe fn get(resources: &'a Resources, _system_id: Option<SystemId>) -> Self::Item {
+        if resources.contains::<T>() {
+            Some(Res::new(resources.get_unsafe_ref::<T>(ResourceIndex::Global)))
+        } else {
+            None
+        }
+    }
+
+    fn borrow(resources: &Resources) {
+        if resources.contains::<T>() {
+            resources.borrow::<T>();
+        }
+    }
+
+    fn release(resources: &Resources) {
+        if resources.contains::<T>() {
+            resources.release::<T>();
+        }
+    }
+
+    fn access() -> TypeAccess {
+        let mut access = TypeAccess::default();
+        access.immutable.insert(ResourceTypeId::of::<T>());
+        access
+    }
+}
+
+impl<'a, T: Resource> ResourceQuery for ChangedRes<'a, T> {
+    type Fetch = FetchResourceChangedRead<T>;
+}
+
+/// Fetches a shared resource reference for change detection
+pub struct FetchResourceChangedRead<T>(NonNull<T>);
+
+impl<T: Resource> FetchResourceChangedRead<T> {
+    /// Returns true if `T` was inserted or mutated since flags were last cleared this update.
+    ///
+    /// `ChangedRes<T>` only ever fetches the `Global` resource slot (see `get` below), so this
+    /// always reads flags for that same slot rather than taking a `SystemId` to index into.
+    pub fn is_changed(resources: &Resources) -> bool {
+        let flags = unsafe { *resources.get_flags::<T>(ResourceIndex::Global).as_ptr() };
+        flags.contains(ComponentFlags::ADDED) || flags.contains(ComponentFlags::MUTATED)
+    }
+}
+
+impl<'a, T: Resource> FetchResource<'a> for FetchResourceChangedRead<T> {
+    type Item = ChangedRes<'a, T>;
+
+    unsafe fn get(resources: &'a Resources, _system_id: Option<SystemId>) -> Self::Item {
+        ChangedRes::new(resources.get_unsafe_ref::<T>(ResourceIndex::Global))
+    }
+
+    fn borrow(resources: &Resources) {
+        resources.borrow::<T>();
+    }
+
+    fn release(resources: &Resources) {
+        resources.release::<T>();
+    }
+
+    fn access() -> TypeAccess {
+        let mut access = TypeAccess::default();
+        // Reading change-detection flags is still only an immutable access to the resource.
+        access.immutable.insert(ResourceTypeId::of::<T>());
         access
     }
 }
@@ -176,7 +455,10 @@ impl<'a, T: Resource> FetchResource<'a> for FetchResourceWrite<T> {
     type Item = ResMut<'a, T>;
 
     unsafe fn get(resources: &'a Resources, _system_id: Option<SystemId>) -> Self::Item {
-        ResMut::new(resources.get_unsafe_ref::<T>(ResourceIndex::Global))
+        ResMut::new(
+            resources.get_unsafe_ref::<T>(ResourceIndex::Global),
+            resources.get_flags::<T>(ResourceIndex::Global),
+        )
     }
 
     fn borrow(resources: &Resources) {
@@ -189,7 +471,107 @@ impl<'a, T: Resource> FetchResource<'a> for FetchResourceWrite<T> {
 
     fn access() -> TypeAccess {
         let mut access = TypeAccess::default();
-        access.mutable.insert(TypeId::of::<T>());
+        access.mutable.insert(ResourceTypeId::of::<T>());
+        access
+    }
+}
+
+impl<'a, T: Resource> ResourceQuery for NonSend<'a, T> {
+    type Fetch = FetchResourceNonSendRead<T>;
+}
+
+/// Fetches a shared reference to a non-`Send` resource
+pub struct FetchResourceNonSendRead<T>(NonNull<T>);
+
+impl<'a, T: Resource> FetchResource<'a> for FetchResourceNonSendRead<T> {
+    type Item = NonSend<'a, T>;
+
+    unsafe fn get(resources: &'a Resources, _system_id: Option<SystemId>) -> Self::Item {
+        NonSend::new(resources.get_unsafe_ref_non_send::<T>())
+    }
+
+    fn borrow(resources: &Resources) {
+        resources.borrow_non_send::<T>();
+    }
+
+    fn release(resources: &Resources) {
+        resources.release_non_send::<T>();
+    }
+
+    fn access() -> TypeAccess {
+        let mut access = TypeAccess::default();
+        access.immutable.insert(ResourceTypeId::of::<T>());
+        access.thread_local.insert(ResourceTypeId::of::<T>());
+        access
+    }
+}
+
+impl<'a, T: Resource> ResourceQuery for NonSendMut<'a, T> {
+    type Fetch = FetchResourceNonSendWrite<T>;
+}
+
+/// Fetches a unique reference to a non-`Send` resource
+pub struct FetchResourceNonSendWrite<T>(NonNull<T>);
+
+impl<'a, T: Resource> FetchResource<'a> for FetchResourceNonSendWrite<T> {
+    type Item = NonSendMut<'a, T>;
+
+    unsafe fn get(resources: &'a Resources, _system_id: Option<SystemId>) -> Self::Item {
+        NonSendMut::new(resources.get_unsafe_ref_non_send::<T>())
+    }
+
+    fn borrow(resources: &Resources) {
+        resources.borrow_mut_non_send::<T>();
+    }
+
+    fn release(resources: &Resources) {
+        resources.release_mut_non_send::<T>();
+    }
+
+    fn access() -> TypeAccess {
+        let mut access = TypeAccess::default();
+        access.mutable.insert(ResourceTypeId::of::<T>());
+        access.thread_local.insert(ResourceTypeId::of::<T>());
+        access
+    }
+}
+
+impl<'a, T: Resource> ResourceQuery for Option<ResMut<'a, T>> {
+    type Fetch = FetchResourceOptionWrite<T>;
+}
+
+/// Fetches a unique resource reference, or `None` if the resource does not exist
+pub struct FetchResourceOptionWrite<T>(NonNull<T>);
+
+impl<'a, T: Resource> FetchResource<'a> for FetchResourceOptionWrite<T> {
+    type Item = Option<ResMut<'a, T>>;
+
+    unsafe fn get(resources: &'a Resources, _system_id: Option<SystemId>) -> Self::Item {
+        if resources.contains::<T>() {
+            Some(ResMut::new(
+                resources.get_unsafe_ref::<T>(ResourceIndex::Global),
+                resources.get_flags::<T>(ResourceIndex::Global),
+            ))
+        } else {
+            None
+        }
+    }
+
+    fn borrow(resources: &Resources) {
+        if resources.contains::<T>() {
+            resources.borrow_mut::<T>();
+        }
+    }
+
+    fn release(resources: &Resources) {
+        if resources.contains::<T>() {
+            resources.release_mut::<T>();
+        }
+    }
+
+    fn access() -> TypeAccess {
+        let mut access = TypeAccess::default();
+        access.mutable.insert(ResourceTypeId::of::<T>());
         access
     }
 }
@@ -199,7 +581,12 @@ impl<'a, T: Resource + FromResources> ResourceQuery for Local<'a, T> {
 
     fn initialize(resources: &mut Resources, id: Option<SystemId>) {
         let value = T::from_resources(resources);
-        let id = id.expect("Local<T> resources can only be used by systems");
+        let id = id.unwrap_or_else(|| {
+            panic!(
+                "Local<{}> resources can only be used by systems",
+                ResourceTypeId::of::<T>()
+            )
+        });
         resources.insert_local(id, value);
     }
 }
@@ -211,11 +598,19 @@ impl<'a, T: Resource + FromResources> FetchResource<'a> for FetchResourceLocalMu
     type Item = Local<'a, T>;
 
     unsafe fn get(resources: &'a Resources, system_id: Option<SystemId>) -> Self::Item {
-        let id = system_id.expect("Local<T> resources can only be used by systems");
+        let id = system_id.unwrap_or_else(|| {
+            panic!(
+                "Local<{}> resources can only be used by systems",
+                ResourceTypeId::of::<T>()
+            )
+        });
         Local {
             value: resources
                 .get_unsafe_ref::<T>(ResourceIndex::System(id))
                 .as_ptr(),
+            flags: resources
+                .get_flags::<T>(ResourceIndex::System(id))
+                .as_ptr(),
             _marker: Default::default(),
         }
     }
@@ -230,7 +625,7 @@ impl<'a, T: Resource + FromResources> FetchResource<'a> for FetchResourceLocalMu
 
     fn access() -> TypeAccess {
         let mut access = TypeAccess::default();
-        access.mutable.insert(TypeId::of::<T>());
+        access.mutable.insert(ResourceTypeId::of::<T>());
         access
     }
 }
@@ -0,0 +1,220 @@
+use super::{ResMut, ResourceEntry};
+use crate::{system::SystemId, ComponentFlags, Resource, ResourceTypeId};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Identifies which slot a resource value lives in: the single [ResourceIndex::Global] slot
+/// shared by every system, or a slot scoped to one [SystemId] (used by [crate::Local]).
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ResourceIndex {
+    Global,
+    System(SystemId),
+}
+
+/// A runtime borrow counter, following the scheme hecs' `AtomicBorrow` uses: any value without
+/// the top bit set is a count of live shared borrows, and the top bit marks a single live unique
+/// borrow.
+struct BorrowState(AtomicUsize);
+
+impl BorrowState {
+    const UNIQUE_BIT: usize = !(usize::MAX >> 1);
+
+    fn new() -> Self {
+        Self(AtomicUsize::new(0))
+    }
+
+    fn borrow(&self) -> bool {
+        let value = self.0.fetch_add(1, Ordering::Acquire).wrapping_add(1);
+        if value & Self::UNIQUE_BIT != 0 {
+            self.0.fetch_sub(1, Ordering::Release);
+            false
+        } else {
+            true
+        }
+    }
+
+    fn borrow_mut(&self) -> bool {
+        self.0
+            .compare_exchange(0, Self::UNIQUE_BIT, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    fn release(&self) {
+        let old = self.0.fetch_sub(1, Ordering::Release);
+        debug_assert_ne!(old, 0, "released a resource that was not borrowed");
+    }
+
+    fn release_mut(&self) {
+        let old = self.0.fetch_and(!Self::UNIQUE_BIT, Ordering::Release);
+        debug_assert_ne!(
+            old & Self::UNIQUE_BIT,
+            0,
+            "released a unique borrow on a resource that was not uniquely borrowed"
+        );
+    }
+}
+
+struct ResourceCell {
+    data: Box<dyn Any>,
+    flags: ComponentFlags,
+    borrow: BorrowState,
+}
+
+impl ResourceCell {
+    fn new(data: Box<dyn Any>) -> Self {
+        Self {
+            data,
+            flags: ComponentFlags::ADDED,
+            borrow: BorrowState::new(),
+        }
+    }
+}
+
+/// The collection of resources available to systems, fetched through [crate::Res]/[crate::ResMut]
+/// (global), [crate::NonSend]/[crate::NonSendMut] (main-thread-only), and [crate::Local]
+/// (per-system).
+#[derive(Default)]
+pub struct Resources {
+    resources: HashMap<TypeId, ResourceCell>,
+    local_resources: HashMap<(TypeId, SystemId), ResourceCell>,
+    non_send_resources: HashMap<TypeId, ResourceCell>,
+}
+
+impl Resources {
+    pub fn insert<T: Resource>(&mut self, value: T) {
+        self.resources
+            .insert(TypeId::of::<T>(), ResourceCell::new(Box::new(value)));
+    }
+
+    pub fn insert_local<T: Resource>(&mut self, id: SystemId, value: T) {
+        self.local_resources
+            .insert((TypeId::of::<T>(), id), ResourceCell::new(Box::new(value)));
+    }
+
+    pub fn insert_non_send<T: Resource>(&mut self, value: T) {
+        self.non_send_resources
+            .insert(TypeId::of::<T>(), ResourceCell::new(Box::new(value)));
+    }
+
+    pub fn contains<T: Resource>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<T>())
+    }
+
+    fn cell<T: Resource>(&self, index: ResourceIndex) -> &ResourceCell {
+        let cell = match index {
+            ResourceIndex::Global => self.resources.get(&TypeId::of::<T>()),
+            ResourceIndex::System(id) => self.local_resources.get(&(TypeId::of::<T>(), id)),
+        };
+        cell.unwrap_or_else(|| panic!("Resource {} does not exist", ResourceTypeId::of::<T>()))
+    }
+
+    fn non_send_cell<T: Resource>(&self) -> &ResourceCell {
+        self.non_send_resources
+            .get(&TypeId::of::<T>())
+            .unwrap_or_else(|| panic!("Resource {} does not exist", ResourceTypeId::of::<T>()))
+    }
+
+    fn value_ptr<T: Resource>(cell: &ResourceCell) -> NonNull<T> {
+        let value = cell
+            .data
+            .downcast_ref::<T>()
+            .expect("resource storage was corrupted");
+        unsafe { NonNull::new_unchecked(value as *const T as *mut T) }
+    }
+
+    pub fn get_unsafe_ref<T: Resource>(&self, index: ResourceIndex) -> NonNull<T> {
+        Self::value_ptr(self.cell::<T>(index))
+    }
+
+    pub fn get_unsafe_ref_non_send<T: Resource>(&self) -> NonNull<T> {
+        Self::value_ptr(self.non_send_cell::<T>())
+    }
+
+    pub fn get_flags<T: Resource>(&self, index: ResourceIndex) -> NonNull<ComponentFlags> {
+        let cell = self.cell::<T>(index);
+        unsafe { NonNull::new_unchecked(&cell.flags as *const ComponentFlags as *mut ComponentFlags) }
+    }
+
+    pub fn borrow<T: Resource>(&self) {
+        if !self.cell::<T>(ResourceIndex::Global).borrow.borrow() {
+            panic!(
+                "Cannot borrow {} immutably while it is already borrowed mutably",
+                ResourceTypeId::of::<T>()
+            );
+        }
+    }
+
+    pub fn borrow_mut<T: Resource>(&self) {
+        if !self.cell::<T>(ResourceIndex::Global).borrow.borrow_mut() {
+            panic!(
+                "Cannot borrow {} mutably while it is already borrowed",
+                ResourceTypeId::of::<T>()
+            );
+        }
+    }
+
+    pub fn release<T: Resource>(&self) {
+        self.cell::<T>(ResourceIndex::Global).borrow.release();
+    }
+
+    pub fn release_mut<T: Resource>(&self) {
+        self.cell::<T>(ResourceIndex::Global).borrow.release_mut();
+    }
+
+    pub fn borrow_non_send<T: Resource>(&self) {
+        if !self.non_send_cell::<T>().borrow.borrow() {
+            panic!(
+                "Cannot borrow {} immutably while it is already borrowed mutably",
+                ResourceTypeId::of::<T>()
+            );
+        }
+    }
+
+    pub fn borrow_mut_non_send<T: Resource>(&self) {
+        if !self.non_send_cell::<T>().borrow.borrow_mut() {
+            panic!(
+                "Cannot borrow {} mutably while it is already borrowed",
+                ResourceTypeId::of::<T>()
+            );
+        }
+    }
+
+    pub fn release_non_send<T: Resource>(&self) {
+        self.non_send_cell::<T>().borrow.release();
+    }
+
+    pub fn release_mut_non_send<T: Resource>(&self) {
+        self.non_send_cell::<T>().borrow.release_mut();
+    }
+
+    /// Returns a view into `T`'s slot that can lazily insert a value if it isn't already present,
+    /// without racing a separate `contains` + `insert`.
+    pub fn entry<T: Resource>(&mut self) -> ResourceEntry<'_, T> {
+        ResourceEntry::new(self)
+    }
+
+    /// Inserts the result of `func` if `T` is not already present, then returns a mutable borrow
+    /// of it. Shorthand for `self.entry::<T>().or_insert_with(func)`.
+    pub fn get_or_insert_with<T: Resource>(&mut self, func: impl FnOnce() -> T) -> ResMut<'_, T> {
+        self.entry::<T>().or_insert_with(func)
+    }
+
+    /// Clears the `ADDED`/`MUTATED` change-detection flags on every resource. The schedule
+    /// executor calls this once at the end of each update, so `ChangedRes<T>` only reports a
+    /// resource as changed during the update(s) it was actually inserted or mutated in.
+    pub fn clear_trackers(&mut self) {
+        for cell in self.resources.values_mut() {
+            cell.flags.clear();
+        }
+        for cell in self.local_resources.values_mut() {
+            cell.flags.clear();
+        }
+        for cell in self.non_send_resources.values_mut() {
+            cell.flags.clear();
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::ResourceTypeId;
+use std::collections::HashSet;
+
+/// Uniquely identifies a system instance, so per-system state (e.g. [crate::Local] resources) can
+/// be scoped to the system that owns it.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct SystemId(pub usize);
+
+/// The set of resource (and component) types a system reads and writes, used to detect when two
+/// systems cannot safely run in parallel.
+#[derive(Default, Debug, Clone)]
+pub struct TypeAccess {
+    pub immutable: HashSet<ResourceTypeId>,
+    pub mutable: HashSet<ResourceTypeId>,
+    /// Types that can only ever be accessed on the thread that owns the `Resources` collection
+    /// (see [crate::NonSend]/[crate::NonSendMut]).
+    pub thread_local: HashSet<ResourceTypeId>,
+}
+
+impl TypeAccess {
+    /// Merges `other`'s access into `self`, panicking with the concrete resource type if the two
+    /// accesses conflict (a mutable access overlapping any other access to the same resource).
+    pub fn union(&mut self, other: &TypeAccess) {
+        for ty in &other.mutable {
+            if self.mutable.contains(ty) {
+                panic!("Cannot borrow {} mutably while it is already borrowed mutably", ty);
+            }
+            if self.immutable.contains(ty) {
+                panic!("Cannot borrow {} mutably while it is already borrowed immutably", ty);
+            }
+        }
+        for ty in &other.immutable {
+            if self.mutable.contains(ty) {
+                panic!("Cannot borrow {} immutably while it is already borrowed mutably", ty);
+            }
+        }
+
+        self.immutable.extend(other.immutable.iter().copied());
+        self.mutable.extend(other.mutable.iter().copied());
+        self.thread_local.extend(other.thread_local.iter().copied());
+    }
+}